@@ -0,0 +1,47 @@
+/// Split one line of RFC-4180-style delimited text into fields, honoring
+/// double-quoted fields (which may contain the delimiter), and `""` as an
+/// escaped quote inside a quoted field. A field is treated as quoted only
+/// when it opens with `"` as its very first character.
+///
+/// Quoted fields spanning multiple physical lines are not supported, since
+/// input is read and grouped one line at a time.
+pub fn parse_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == '"' && field.is_empty() {
+            in_quotes = true;
+        } else if c == delimiter {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(c);
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Resolve a possibly-negative column index (`-1` = last column) against a
+/// row of `len` fields. Returns `None` if the resolved index is out of bounds.
+pub fn resolve_index(index: isize, len: usize) -> Option<usize> {
+    let resolved = if index < 0 { index + len as isize } else { index };
+    if resolved >= 0 && (resolved as usize) < len {
+        Some(resolved as usize)
+    } else {
+        None
+    }
+}
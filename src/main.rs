@@ -1,38 +1,63 @@
+mod csv_record;
 mod number_stats;
 mod output_number_data;
 mod output_row;
 mod output_string_data;
 mod string_stats;
 
-use clap::{CommandFactory, Parser};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use csv_record::{parse_record, resolve_index};
 use is_terminal::IsTerminal as _;
-use number_stats::NumberStats;
+use number_stats::{NumberStats, DEFAULT_QUANTILES};
 use output_number_data::OutputNumberData;
+use output_row::OutputFormat;
 use output_string_data::OutputStringData;
 use std::collections::HashMap;
 use std::{
     fs::File,
     io::{stdin, BufRead, BufReader},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
-use string_stats::StringStats;
+use string_stats::{CardinalityMode, StringStats};
 
-type GroupNumberStats = HashMap<String, NumberStats>;
-type GroupStringStats = HashMap<String, (StringStats, NumberStats)>;
+type GroupNumberStats = HashMap<Vec<String>, NumberStats>;
+type GroupStringStats = HashMap<Vec<String>, (StringStats, NumberStats)>;
+
+/// Structured output formats, serialized one group per array entry (`json`)
+/// or one group per line (`ndjson`).
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum FormatArg {
+    Json,
+    Ndjson,
+}
 
 /// Grouped number stats on stream (count, min, max, mean, stddev).
-/// Takes the last column of the provided data as the number value to analyze.
-/// All preceding columns are interpreted as grouping data.
+/// Parses each line as RFC-4180-style delimited fields (quoted fields may
+/// contain the delimiter). By default the last column is the value to
+/// analyze and all other columns are the grouping key.
 #[derive(Parser)]
 struct Cli {
     /// input delimiter
     #[arg(short = 'd', long)]
     input_delimiter: char,
 
+    /// Index of the value column to analyze, negative counts from the end
+    #[arg(long, allow_hyphen_values = true, default_value_t = -1)]
+    value_column: isize,
+
+    /// Comma-separated indices of the grouping columns, negative counts from
+    /// the end; defaults to every column except the value column
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    group_columns: Option<Vec<isize>>,
+
     /// Optional output delimiter, default to human readable table output
     #[arg(short = 'D', long)]
     output_delimiter: Option<char>,
 
+    /// Output format; overrides --output-delimiter when set
+    #[arg(long)]
+    format: Option<FormatArg>,
+
     /// Optional number of decimals to round for output
     #[arg(short = 'r', long, default_value_t = 0)]
     decimals: usize,
@@ -46,14 +71,34 @@ struct Cli {
     #[arg(short, long, default_value_t = false)]
     strings: bool,
 
-    /// Optional cap on cardinality, set to zero to disable cardinality
+    /// Optional cap on cardinality; 0 switches to approximate HyperLogLog
+    /// cardinality instead of an exact capped set
     #[arg(short, long)]
     cardinality_cap: Option<usize>,
 
+    /// Track cardinality approximately via HyperLogLog, in fixed memory
+    #[arg(long, default_value_t = false)]
+    hll: bool,
+
+    /// Report the top N most frequent values per group (Space-Saving heavy hitters)
+    #[arg(long)]
+    top_k: Option<usize>,
+
+    /// Track quantiles (median, p25, p75, p95) via the online P² estimator.
+    /// Not compatible with --threads > 1: the estimator cannot be merged
+    /// across shards.
+    #[arg(short, long, default_value_t = false)]
+    quantiles: bool,
+
     /// Count empty strings as null, in addition to always countint non-numbers as null
     #[arg(short, long, default_value_t = false)]
     empty_as_null: bool,
 
+    /// Number of worker threads to aggregate with; splits input into chunks,
+    /// aggregates each independently, then merges the partial results
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+
     /// The path to the file to read, use - to read from stdin (must not be a tty)
     #[arg(default_value = "-")]
     file: PathBuf,
@@ -61,58 +106,110 @@ struct Cli {
 
 fn main() {
     let args = Cli::parse();
+    if !args.strings && args.quantiles && args.threads > 1 {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--quantiles cannot be combined with --threads > 1: the P² estimator is not mergeable across \
+                 shards, so the reported quantiles would reflect a single shard's data rather than the whole stream",
+            )
+            .exit();
+    }
+    if args.top_k == Some(0) {
+        Cli::command()
+            .error(ErrorKind::InvalidValue, "--top-k 0 would track no heavy hitters at all; pass a value of 1 or more")
+            .exit();
+    }
     let file = args.file;
+    let output_format = match (args.format, args.output_delimiter) {
+        (Some(FormatArg::Json), _) => OutputFormat::Json,
+        (Some(FormatArg::Ndjson), _) => OutputFormat::Ndjson,
+        (None, Some(delimiter)) => OutputFormat::Delimited(delimiter),
+        (None, None) => OutputFormat::Table,
+    };
 
     if args.strings {
-        let group_string_stats = if file == PathBuf::from("-") {
+        let cardinality_mode = if args.hll || args.cardinality_cap == Some(0) {
+            CardinalityMode::Hll
+        } else {
+            CardinalityMode::Exact(args.cardinality_cap)
+        };
+        let buf_reader: Box<dyn BufRead> = if file == Path::new("-") {
             if stdin().is_terminal() {
                 Cli::command().print_help().unwrap();
                 ::std::process::exit(2);
             }
-            group_string_stats_in_buf_reader(
-                BufReader::new(stdin().lock()),
+            Box::new(BufReader::new(stdin().lock()))
+        } else {
+            Box::new(BufReader::new(File::open(&file).unwrap()))
+        };
+        let group_string_stats = if args.threads > 1 {
+            let lines: Vec<String> = buf_reader.lines().collect::<Result<_, _>>().unwrap();
+            group_string_stats_parallel(
+                &lines,
                 args.input_delimiter,
                 args.empty_as_null,
-                args.cardinality_cap,
+                &cardinality_mode,
+                args.top_k,
+                args.value_column,
+                args.group_columns.as_deref(),
+                args.threads,
             )
         } else {
             group_string_stats_in_buf_reader(
-                BufReader::new(File::open(&file).unwrap()),
+                buf_reader,
                 args.input_delimiter,
                 args.empty_as_null,
-                args.cardinality_cap,
+                &cardinality_mode,
+                args.top_k,
+                args.value_column,
+                args.group_columns.as_deref(),
             )
         };
         OutputStringData::new(
             group_string_stats,
             args.input_delimiter,
-            args.output_delimiter,
+            output_format,
             args.decimals,
-            args.cardinality_cap,
+            args.top_k,
         )
         .print();
     } else {
-        let group_number_stats = if file == PathBuf::from("-") {
+        let quantiles: &[f64] = if args.quantiles { &DEFAULT_QUANTILES } else { &[] };
+        let buf_reader: Box<dyn BufRead> = if file == Path::new("-") {
             if stdin().is_terminal() {
                 Cli::command().print_help().unwrap();
                 ::std::process::exit(2);
             }
-            group_number_stats_in_buf_reader(
-                BufReader::new(stdin().lock()),
+            Box::new(BufReader::new(stdin().lock()))
+        } else {
+            Box::new(BufReader::new(File::open(&file).unwrap()))
+        };
+        let group_number_stats = if args.threads > 1 {
+            let lines: Vec<String> = buf_reader.lines().collect::<Result<_, _>>().unwrap();
+            group_number_stats_parallel(
+                &lines,
                 args.input_delimiter,
                 args.zero_as_null,
+                quantiles,
+                args.value_column,
+                args.group_columns.as_deref(),
+                args.threads,
             )
         } else {
             group_number_stats_in_buf_reader(
-                BufReader::new(File::open(&file).unwrap()),
+                buf_reader,
                 args.input_delimiter,
                 args.zero_as_null,
+                quantiles,
+                args.value_column,
+                args.group_columns.as_deref(),
             )
         };
         OutputNumberData::new(
             group_number_stats,
             args.input_delimiter,
-            args.output_delimiter,
+            output_format,
             args.decimals,
         )
         .print();
@@ -123,62 +220,369 @@ fn group_number_stats_in_buf_reader<R: BufRead>(
     buf_reader: R,
     delimiter: char,
     zero_as_null: bool,
+    quantiles: &[f64],
+    value_column: isize,
+    group_columns: Option<&[isize]>,
 ) -> GroupNumberStats {
     let mut group_number_stats = GroupNumberStats::new();
     for line in buf_reader.lines() {
-        let raw = line.unwrap();
-        match raw.rsplit_once(delimiter) {
-            Some((group, value)) => {
-                let number_stats = group_number_stats
-                    .entry(group.to_string())
-                    .or_insert(NumberStats::new());
-                match value.parse::<f64>() {
-                    Ok(num) if zero_as_null && num == 0.0 => number_stats.add_null(),
-                    Ok(num) => number_stats.add(num),
-                    Err(_) => number_stats.add_null(),
-                };
-            }
-            None => {
-                group_number_stats
-                    .entry("<INVALID>".to_string())
-                    .and_modify(|number_stats| number_stats.add_null())
-                    .or_insert(NumberStats::new());
-            }
-        }
+        fold_number_line(
+            &mut group_number_stats,
+            &line.unwrap(),
+            delimiter,
+            zero_as_null,
+            quantiles,
+            value_column,
+            group_columns,
+        );
     }
     group_number_stats
 }
 
+fn group_number_stats_in_lines(
+    lines: &[String],
+    delimiter: char,
+    zero_as_null: bool,
+    quantiles: &[f64],
+    value_column: isize,
+    group_columns: Option<&[isize]>,
+) -> GroupNumberStats {
+    let mut group_number_stats = GroupNumberStats::new();
+    for raw in lines {
+        fold_number_line(
+            &mut group_number_stats,
+            raw,
+            delimiter,
+            zero_as_null,
+            quantiles,
+            value_column,
+            group_columns,
+        );
+    }
+    group_number_stats
+}
+
+/// Split `lines` into `threads` roughly-equal chunks, fold each chunk into
+/// an independent [`GroupNumberStats`] partial on its own thread, then merge
+/// the partials via [`NumberStats::merge`].
+fn group_number_stats_parallel(
+    lines: &[String],
+    delimiter: char,
+    zero_as_null: bool,
+    quantiles: &[f64],
+    value_column: isize,
+    group_columns: Option<&[isize]>,
+    threads: usize,
+) -> GroupNumberStats {
+    let chunk_size = lines.len().div_ceil(threads).max(1);
+    let partials: Vec<GroupNumberStats> = std::thread::scope(|scope| {
+        lines
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(|| {
+                    group_number_stats_in_lines(chunk, delimiter, zero_as_null, quantiles, value_column, group_columns)
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    merge_group_number_stats(partials)
+}
+
+fn merge_group_number_stats(partials: Vec<GroupNumberStats>) -> GroupNumberStats {
+    let mut merged = GroupNumberStats::new();
+    for partial in partials {
+        for (group, stats) in partial {
+            merged
+                .entry(group)
+                .and_modify(|existing| existing.merge(&stats))
+                .or_insert(stats);
+        }
+    }
+    merged
+}
+
+fn fold_number_line(
+    group_number_stats: &mut GroupNumberStats,
+    raw: &str,
+    delimiter: char,
+    zero_as_null: bool,
+    quantiles: &[f64],
+    value_column: isize,
+    group_columns: Option<&[isize]>,
+) {
+    let fields = parse_record(raw, delimiter);
+    match select_group_and_value(&fields, value_column, group_columns) {
+        Some((group, value)) => {
+            let number_stats = group_number_stats.entry(group).or_insert(NumberStats::new(quantiles));
+            match value.parse::<f64>() {
+                Ok(num) if zero_as_null && num == 0.0 => number_stats.add_null(),
+                Ok(num) => number_stats.add(num),
+                Err(_) => number_stats.add_null(),
+            };
+        }
+        None => {
+            group_number_stats
+                .entry(vec!["<INVALID>".to_string()])
+                .and_modify(|number_stats| number_stats.add_null())
+                .or_insert(NumberStats::new(quantiles));
+        }
+    }
+}
+
 fn group_string_stats_in_buf_reader<R: BufRead>(
     buf_reader: R,
     delimiter: char,
     empty_as_null: bool,
-    cardinality_cap: Option<usize>,
+    cardinality_mode: &CardinalityMode,
+    top_k: Option<usize>,
+    value_column: isize,
+    group_columns: Option<&[isize]>,
 ) -> GroupStringStats {
     let mut group_string_stats = GroupStringStats::new();
     for line in buf_reader.lines() {
-        let raw = line.unwrap();
-        match raw.rsplit_once(delimiter) {
-            Some((group, value)) => {
-                let (value_stats, length_stats) = group_string_stats
-                    .entry(group.to_string())
-                    .or_insert((StringStats::new(cardinality_cap), NumberStats::new()));
-
-                if empty_as_null && value.is_empty() {
-                    length_stats.add_null();
-                    value_stats.add_null();
-                } else {
-                    length_stats.add(value.len() as f64);
-                    value_stats.add(value.to_string());
-                };
+        fold_string_line(
+            &mut group_string_stats,
+            &line.unwrap(),
+            delimiter,
+            empty_as_null,
+            cardinality_mode,
+            top_k,
+            value_column,
+            group_columns,
+        );
+    }
+    group_string_stats
+}
+
+fn group_string_stats_in_lines(
+    lines: &[String],
+    delimiter: char,
+    empty_as_null: bool,
+    cardinality_mode: &CardinalityMode,
+    top_k: Option<usize>,
+    value_column: isize,
+    group_columns: Option<&[isize]>,
+) -> GroupStringStats {
+    let mut group_string_stats = GroupStringStats::new();
+    for raw in lines {
+        fold_string_line(
+            &mut group_string_stats,
+            raw,
+            delimiter,
+            empty_as_null,
+            cardinality_mode,
+            top_k,
+            value_column,
+            group_columns,
+        );
+    }
+    group_string_stats
+}
+
+/// Split `lines` into `threads` roughly-equal chunks, fold each chunk into
+/// an independent [`GroupStringStats`] partial on its own thread, then merge
+/// the partials via [`StringStats::merge`] / [`NumberStats::merge`].
+#[allow(clippy::too_many_arguments)]
+fn group_string_stats_parallel(
+    lines: &[String],
+    delimiter: char,
+    empty_as_null: bool,
+    cardinality_mode: &CardinalityMode,
+    top_k: Option<usize>,
+    value_column: isize,
+    group_columns: Option<&[isize]>,
+    threads: usize,
+) -> GroupStringStats {
+    let chunk_size = lines.len().div_ceil(threads).max(1);
+    let partials: Vec<GroupStringStats> = std::thread::scope(|scope| {
+        lines
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(|| {
+                    group_string_stats_in_lines(
+                        chunk,
+                        delimiter,
+                        empty_as_null,
+                        cardinality_mode,
+                        top_k,
+                        value_column,
+                        group_columns,
+                    )
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    merge_group_string_stats(partials)
+}
+
+fn merge_group_string_stats(partials: Vec<GroupStringStats>) -> GroupStringStats {
+    let mut merged = GroupStringStats::new();
+    for partial in partials {
+        for (group, (value_stats, length_stats)) in partial {
+            merged
+                .entry(group)
+                .and_modify(|(existing_value, existing_length)| {
+                    existing_value.merge(&value_stats);
+                    existing_length.merge(&length_stats);
+                })
+                .or_insert((value_stats, length_stats));
+        }
+    }
+    merged
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fold_string_line(
+    group_string_stats: &mut GroupStringStats,
+    raw: &str,
+    delimiter: char,
+    empty_as_null: bool,
+    cardinality_mode: &CardinalityMode,
+    top_k: Option<usize>,
+    value_column: isize,
+    group_columns: Option<&[isize]>,
+) {
+    let fields = parse_record(raw, delimiter);
+    match select_group_and_value(&fields, value_column, group_columns) {
+        Some((group, value)) => {
+            let (value_stats, length_stats) = group_string_stats
+                .entry(group)
+                .or_insert_with(|| (StringStats::new(*cardinality_mode, top_k), NumberStats::new(&[])));
+
+            if empty_as_null && value.is_empty() {
+                length_stats.add_null();
+                value_stats.add_null();
+            } else {
+                length_stats.add(value.len() as f64);
+                value_stats.add(value.to_string());
+            };
+        }
+        None => {
+            group_string_stats
+                .entry(vec!["<INVALID>".to_string()])
+                .and_modify(|(value_stats, _length_stats)| value_stats.add_null())
+                .or_insert_with(|| (StringStats::new(*cardinality_mode, top_k), NumberStats::new(&[])));
+        }
+    }
+}
+
+/// Resolve the value column and grouping columns against one parsed row,
+/// returning the group key as its original field values (not re-joined, so
+/// a group column's own value can safely contain the delimiter) and the
+/// value field. Returns `None` if the value column is out of bounds, if an
+/// explicit group column is out of bounds, or if the default
+/// (every-other-column) grouping has nothing left to group by — e.g. a
+/// single-field row with no delimiter at all.
+fn select_group_and_value(
+    fields: &[String],
+    value_column: isize,
+    group_columns: Option<&[isize]>,
+) -> Option<(Vec<String>, String)> {
+    let value_index = resolve_index(value_column, fields.len())?;
+    let group_indices: Vec<usize> = match group_columns {
+        Some(columns) => columns
+            .iter()
+            .map(|&column| resolve_index(column, fields.len()))
+            .collect::<Option<Vec<_>>>()?,
+        None => {
+            let indices: Vec<usize> = (0..fields.len()).filter(|&index| index != value_index).collect();
+            if indices.is_empty() {
+                return None;
             }
-            None => {
-                group_string_stats
-                    .entry("<INVALID>".to_string())
-                    .and_modify(|(value_stats, _length_stats)| value_stats.add_null())
-                    .or_insert((StringStats::new(cardinality_cap), NumberStats::new()));
+            indices
+        }
+    };
+
+    let group = group_indices.iter().map(|&index| fields[index].clone()).collect();
+    Some((group, fields[value_index].clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sharding the input and merging partials must agree with folding the
+    /// whole stream in one pass, for every stat that is actually mergeable
+    /// (count/min/max/mean/stddev; quantiles are intentionally excluded from
+    /// the threaded path, see the `--quantiles`/`--threads` CLI check).
+    #[test]
+    fn merge_group_number_stats_matches_single_pass() {
+        let lines: Vec<String> = (1..=1000).map(|i| format!("g{},{i}", i % 3)).collect();
+
+        let single_pass = group_number_stats_in_lines(&lines, ',', false, &[], -1, None);
+
+        let partials: Vec<GroupNumberStats> = lines
+            .chunks(37)
+            .map(|chunk| group_number_stats_in_lines(chunk, ',', false, &[], -1, None))
+            .collect();
+        let merged = merge_group_number_stats(partials);
+
+        assert_eq!(single_pass.len(), merged.len());
+        for (group, expected) in &single_pass {
+            let actual = merged.get(group).unwrap();
+            assert_eq!(actual.count(), expected.count());
+            assert_eq!(actual.min(), expected.min());
+            assert_eq!(actual.max(), expected.max());
+            assert!((actual.mean() - expected.mean()).abs() < 1e-9);
+            assert!((actual.stddev() - expected.stddev()).abs() < 1e-9);
+        }
+    }
+
+    /// Mirrors [`merge_group_number_stats_matches_single_pass`] for the
+    /// string side: sharding and merging must agree with a single pass for
+    /// cardinality (both exact, comfortably under its cap so shard
+    /// boundaries can't change which values got dropped, and HyperLogLog,
+    /// whose register-wise max merge is exact regardless of chunking) and
+    /// for top-k (capacity comfortably above the distinct count so no
+    /// shard evicts, making the merged counts exact).
+    #[test]
+    fn merge_group_string_stats_matches_single_pass() {
+        // Skewed so each group's top-3 values have distinct counts; a flat
+        // distribution produces ties at the cutoff whose order depends on
+        // HashMap iteration, which differs between a single pass and a
+        // merge of shard partials even though both results are correct.
+        let lines: Vec<String> = (1..=1000)
+            .map(|i| {
+                let value = match i % 20 {
+                    0..=9 => 0,
+                    10..=14 => 1,
+                    15..=17 => 2,
+                    18 => 3,
+                    _ => 4,
+                };
+                format!("g{},value{value}", i % 3)
+            })
+            .collect();
+        let top_k = Some(3);
+
+        for cardinality_mode in [CardinalityMode::Exact(Some(1000)), CardinalityMode::Hll] {
+            let single_pass = group_string_stats_in_lines(&lines, ',', false, &cardinality_mode, top_k, -1, None);
+
+            let partials: Vec<GroupStringStats> = lines
+                .chunks(37)
+                .map(|chunk| group_string_stats_in_lines(chunk, ',', false, &cardinality_mode, top_k, -1, None))
+                .collect();
+            let merged = merge_group_string_stats(partials);
+
+            assert_eq!(single_pass.len(), merged.len());
+            for (group, (expected_value, expected_length)) in &single_pass {
+                let (actual_value, actual_length) = merged.get(group).unwrap();
+                assert_eq!(actual_value.cardinality(), expected_value.cardinality());
+                assert_eq!(actual_value.null_count(), expected_value.null_count());
+                assert_eq!(actual_length.count(), expected_length.count());
+
+                let mut expected_top_k = expected_value.top_k(3).unwrap();
+                let mut actual_top_k = actual_value.top_k(3).unwrap();
+                expected_top_k.sort();
+                actual_top_k.sort();
+                assert_eq!(actual_top_k, expected_top_k);
             }
         }
     }
-    group_string_stats
 }
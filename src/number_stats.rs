@@ -0,0 +1,307 @@
+/// Default quantiles tracked when `--quantiles` is enabled: median, the
+/// interquartile bounds, and a tail percentile.
+pub const DEFAULT_QUANTILES: [f64; 4] = [0.25, 0.5, 0.75, 0.95];
+
+/// Online quantile estimator using the P² (Piecewise-Parabolic) algorithm.
+///
+/// Keeps five markers (min, ~p/2, ~p, ~(1+p)/2, max) and adjusts their
+/// heights as observations arrive, so a single target quantile `p` can be
+/// estimated in O(1) memory without storing the stream. See Jain & Chlamtac,
+/// "The P² Algorithm for Dynamic Calculation of Quantiles and Histograms
+/// Without Storing Observations" (1985).
+#[derive(Debug, Clone)]
+struct P2Estimator {
+    p: f64,
+    init: Vec<f64>,
+    q: [f64; 5],
+    n: [i64; 5],
+    np: [f64; 5],
+    dn: [f64; 5],
+}
+
+impl P2Estimator {
+    fn new(p: f64) -> Self {
+        P2Estimator {
+            p,
+            init: Vec::with_capacity(5),
+            q: [0.0; 5],
+            n: [0; 5],
+            np: [0.0; 5],
+            dn: [0.0; 5],
+        }
+    }
+
+    fn add(&mut self, x: f64) {
+        if self.init.len() < 5 {
+            self.init.push(x);
+            if self.init.len() == 5 {
+                self.init.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let p = self.p;
+                for i in 0..5 {
+                    self.q[i] = self.init[i];
+                    self.n[i] = (i + 1) as i64;
+                }
+                self.np = [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0];
+                self.dn = [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0];
+            }
+            return;
+        }
+
+        let k = if x < self.q[0] {
+            self.q[0] = x;
+            0
+        } else if x < self.q[1] {
+            0
+        } else if x < self.q[2] {
+            1
+        } else if x < self.q[3] {
+            2
+        } else if x <= self.q[4] {
+            3
+        } else {
+            self.q[4] = x;
+            3
+        };
+
+        for n in self.n.iter_mut().skip(k + 1) {
+            *n += 1;
+        }
+        for i in 0..5 {
+            self.np[i] += self.dn[i];
+        }
+
+        for i in 1..4 {
+            let d = self.np[i] - self.n[i] as f64;
+            if (d >= 1.0 && self.n[i + 1] - self.n[i] > 1) || (d <= -1.0 && self.n[i - 1] - self.n[i] < -1) {
+                let d = if d >= 0.0 { 1.0 } else { -1.0 };
+                let parabolic = self.parabolic(i, d);
+                self.q[i] = if self.q[i - 1] < parabolic && parabolic < self.q[i + 1] {
+                    parabolic
+                } else {
+                    self.linear(i, d)
+                };
+                self.n[i] += d as i64;
+            }
+        }
+    }
+
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        let (n, q) = (&self.n, &self.q);
+        let (n_prev, n_cur, n_next) = (n[i - 1] as f64, n[i] as f64, n[i + 1] as f64);
+        q[i] + d / (n_next - n_prev)
+            * ((n_cur - n_prev + d) * (q[i + 1] - q[i]) / (n_next - n_cur)
+                + (n_next - n_cur - d) * (q[i] - q[i - 1]) / (n_cur - n_prev))
+    }
+
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+        self.q[i] + d * (self.q[j] - self.q[i]) / (self.n[j] - self.n[i]) as f64
+    }
+
+    fn value(&self) -> f64 {
+        if self.init.is_empty() {
+            return 0.0;
+        }
+        if self.init.len() < 5 {
+            let mut sorted = self.init.clone();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let idx = (((sorted.len() - 1) as f64) * self.p).round() as usize;
+            return sorted[idx];
+        }
+        self.q[2]
+    }
+}
+
+/// Online (streaming) statistics for a single group of numeric values.
+///
+/// Count, min, max and mean/stddev are tracked exactly via Welford's
+/// algorithm; optional quantiles are tracked approximately via the P²
+/// estimator so the whole stream never needs to be held in memory.
+#[derive(Debug, Clone)]
+pub struct NumberStats {
+    count: usize,
+    null_count: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+    quantiles: Vec<(f64, P2Estimator)>,
+}
+
+impl NumberStats {
+    /// Create a new accumulator. `quantiles` lists the target quantiles
+    /// (e.g. `0.5` for the median) to track online; pass an empty slice to
+    /// skip quantile tracking entirely.
+    pub fn new(quantiles: &[f64]) -> Self {
+        NumberStats {
+            count: 0,
+            null_count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+            quantiles: quantiles.iter().map(|&p| (p, P2Estimator::new(p))).collect(),
+        }
+    }
+
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        if value < self.min {
+            self.min = value;
+        }
+        if value > self.max {
+            self.max = value;
+        }
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        let delta2 = value - self.mean;
+        self.m2 += delta * delta2;
+
+        for (_, estimator) in &mut self.quantiles {
+            estimator.add(value);
+        }
+    }
+
+    pub fn add_null(&mut self) {
+        self.null_count += 1;
+    }
+
+    /// Fold `other`'s observations into `self`. Count, null count, min and
+    /// max combine directly; mean and stddev combine via the parallel
+    /// Welford/Chan update. Quantile estimators are not mergeable online, so
+    /// `self`'s quantile estimates are left as-is.
+    pub fn merge(&mut self, other: &NumberStats) {
+        self.null_count += other.null_count;
+        if other.count == 0 {
+            return;
+        }
+        if other.min < self.min {
+            self.min = other.min;
+        }
+        if other.max > self.max {
+            self.max = other.max;
+        }
+
+        let n_a = self.count as f64;
+        let n_b = other.count as f64;
+        let n = n_a + n_b;
+        let delta = other.mean - self.mean;
+        self.mean += delta * n_b / n;
+        self.m2 += other.m2 + delta * delta * n_a * n_b / n;
+        self.count += other.count;
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.null_count
+    }
+
+    pub fn min(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.min
+        }
+    }
+
+    pub fn max(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.max
+        }
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn stddev(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            (self.m2 / self.count as f64).sqrt()
+        }
+    }
+
+    /// All tracked quantiles as `(p, estimate)` pairs, in the order given to [`NumberStats::new`].
+    pub fn quantiles(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.quantiles.iter().map(|(p, estimator)| (*p, estimator.value()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed 1..=1001 in order; P² should land within a couple of percent of
+    /// the exact rank-based quantile on this well-behaved distribution.
+    #[test]
+    fn p2_quantiles_match_uniform_distribution() {
+        let mut stats = NumberStats::new(&DEFAULT_QUANTILES);
+        for i in 1..=1001 {
+            stats.add(i as f64);
+        }
+
+        let expected = [(0.25, 251.0), (0.5, 501.0), (0.75, 751.0), (0.95, 951.0)];
+        for ((p, estimate), (expected_p, expected_value)) in stats.quantiles().zip(expected) {
+            assert_eq!(p, expected_p);
+            assert!(
+                (estimate - expected_value).abs() < expected_value * 0.02,
+                "p{p}: expected ~{expected_value}, got {estimate}"
+            );
+        }
+    }
+
+    #[test]
+    fn p2_median_is_stable_under_input_order() {
+        let mut ascending = NumberStats::new(&[0.5]);
+        for i in 1..=1001 {
+            ascending.add(i as f64);
+        }
+        let mut shuffled = NumberStats::new(&[0.5]);
+        for i in (1..=1001).rev() {
+            shuffled.add(i as f64);
+        }
+
+        let ascending_median = ascending.quantiles().next().unwrap().1;
+        let shuffled_median = shuffled.quantiles().next().unwrap().1;
+        assert!((ascending_median - shuffled_median).abs() < 10.0);
+    }
+
+    #[test]
+    fn fewer_than_five_samples_falls_back_to_exact_rank() {
+        let mut stats = NumberStats::new(&[0.5]);
+        stats.add(3.0);
+        stats.add(1.0);
+        stats.add(2.0);
+        assert_eq!(stats.quantiles().next().unwrap().1, 2.0);
+    }
+
+    #[test]
+    fn merge_combines_count_min_max_mean_exactly() {
+        let mut a = NumberStats::new(&[]);
+        for v in [1.0, 2.0, 3.0] {
+            a.add(v);
+        }
+        let mut b = NumberStats::new(&[]);
+        for v in [4.0, 5.0, 6.0] {
+            b.add(v);
+        }
+        a.merge(&b);
+
+        assert_eq!(a.count(), 6);
+        assert_eq!(a.min(), 1.0);
+        assert_eq!(a.max(), 6.0);
+        assert_eq!(a.mean(), 3.5);
+
+        let mut single = NumberStats::new(&[]);
+        for v in [1.0, 2.0, 3.0, 4.0, 5.0, 6.0] {
+            single.add(v);
+        }
+        assert!((a.stddev() - single.stddev()).abs() < 1e-9);
+    }
+}
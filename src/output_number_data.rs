@@ -0,0 +1,110 @@
+use crate::output_row::{print_table, round, OutputFormat, OutputRow};
+use crate::GroupNumberStats;
+
+/// Renders a [`GroupNumberStats`] map as a human-readable table, a
+/// delimiter-separated dump, or JSON/NDJSON, one entry per group, sorted by
+/// group key.
+pub struct OutputNumberData {
+    headers: Vec<String>,
+    rows: Vec<OutputRow>,
+    json_rows: Vec<serde_json::Value>,
+    output_format: OutputFormat,
+}
+
+impl OutputNumberData {
+    pub fn new(
+        group_number_stats: GroupNumberStats,
+        input_delimiter: char,
+        output_format: OutputFormat,
+        decimals: usize,
+    ) -> Self {
+        let mut groups: Vec<_> = group_number_stats.into_iter().collect();
+        groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut headers = vec![
+            "group".to_string(),
+            "count".to_string(),
+            "null_count".to_string(),
+            "min".to_string(),
+            "max".to_string(),
+            "mean".to_string(),
+            "stddev".to_string(),
+        ];
+        if let Some((_, first)) = groups.first() {
+            for (p, _) in first.quantiles() {
+                headers.push(quantile_header(p));
+            }
+        }
+
+        let mut rows = Vec::with_capacity(groups.len());
+        let mut json_rows = Vec::with_capacity(groups.len());
+        for (group, stats) in groups {
+            let mut values = vec![
+                stats.count().to_string(),
+                stats.null_count().to_string(),
+                format!("{:.*}", decimals, stats.min()),
+                format!("{:.*}", decimals, stats.max()),
+                format!("{:.*}", decimals, stats.mean()),
+                format!("{:.*}", decimals, stats.stddev()),
+            ];
+            for (_, estimate) in stats.quantiles() {
+                values.push(format!("{:.*}", decimals, estimate));
+            }
+
+            let quantiles: serde_json::Map<String, serde_json::Value> = stats
+                .quantiles()
+                .map(|(p, estimate)| (quantile_header(p), round(estimate, decimals).into()))
+                .collect();
+            let mut json_row = serde_json::json!({
+                "group": group.clone(),
+                "count": stats.count(),
+                "null_count": stats.null_count(),
+                "min": round(stats.min(), decimals),
+                "max": round(stats.max(), decimals),
+                "mean": round(stats.mean(), decimals),
+                "stddev": round(stats.stddev(), decimals),
+            });
+            if !quantiles.is_empty() {
+                json_row["quantiles"] = quantiles.into();
+            }
+
+            rows.push(OutputRow::new(group, input_delimiter, values));
+            json_rows.push(json_row);
+        }
+
+        OutputNumberData {
+            headers,
+            rows,
+            json_rows,
+            output_format,
+        }
+    }
+
+    pub fn print(&self) {
+        match self.output_format {
+            OutputFormat::Table => print_table(&self.headers, &self.rows),
+            OutputFormat::Delimited(delimiter) => {
+                println!("{}", self.headers.join(&delimiter.to_string()));
+                for row in &self.rows {
+                    println!("{}", row.to_delimited(delimiter));
+                }
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&self.json_rows).unwrap());
+            }
+            OutputFormat::Ndjson => {
+                for row in &self.json_rows {
+                    println!("{}", serde_json::to_string(row).unwrap());
+                }
+            }
+        }
+    }
+}
+
+fn quantile_header(p: f64) -> String {
+    if p == 0.5 {
+        "median".to_string()
+    } else {
+        format!("p{}", (p * 100.0).round() as i64)
+    }
+}
@@ -0,0 +1,76 @@
+/// How grouped output is rendered.
+#[derive(Debug, Clone, Copy)]
+pub enum OutputFormat {
+    /// Human-readable table, columns padded to their widest value.
+    Table,
+    /// One delimiter-joined line per group, headers on the first line.
+    Delimited(char),
+    /// A single JSON array of per-group objects.
+    Json,
+    /// One compact JSON object per group, one per line.
+    Ndjson,
+}
+
+/// Round `value` to `decimals` fractional digits, keeping it a number
+/// (rather than a formatted string) for JSON output.
+pub fn round(value: f64, decimals: usize) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    (value * factor).round() / factor
+}
+
+/// A single rendered line of grouped output: the group key columns followed
+/// by the computed stat columns, already formatted as strings.
+///
+/// Shared between [`crate::output_number_data::OutputNumberData`] and
+/// [`crate::output_string_data::OutputStringData`] so both can reuse the
+/// same table/delimited rendering. `group_display` joins the original group
+/// field values with `delimiter`, which is fine for human-readable output
+/// but (unlike the original field values) not safe to split back apart —
+/// JSON output reports the group fields directly instead of going through
+/// this struct.
+pub struct OutputRow {
+    pub group_display: String,
+    pub values: Vec<String>,
+}
+
+impl OutputRow {
+    pub fn new(group_fields: Vec<String>, delimiter: char, values: Vec<String>) -> Self {
+        OutputRow {
+            group_display: group_fields.join(&delimiter.to_string()),
+            values,
+        }
+    }
+
+    pub fn to_delimited(&self, delimiter: char) -> String {
+        std::iter::once(self.group_display.clone())
+            .chain(self.values.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(&delimiter.to_string())
+    }
+}
+
+/// Print `headers`/`rows` as a human-readable table with columns padded to
+/// the widest value seen in that column.
+pub fn print_table(headers: &[String], rows: &[OutputRow]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows {
+        widths[0] = widths[0].max(row.group_display.len());
+        for (i, value) in row.values.iter().enumerate() {
+            widths[i + 1] = widths[i + 1].max(value.len());
+        }
+    }
+
+    print_padded_row(headers.iter().map(String::as_str), &widths);
+    for row in rows {
+        let cells = std::iter::once(row.group_display.as_str()).chain(row.values.iter().map(String::as_str));
+        print_padded_row(cells, &widths);
+    }
+}
+
+fn print_padded_row<'a>(cells: impl Iterator<Item = &'a str>, widths: &[usize]) {
+    let line: Vec<String> = cells
+        .enumerate()
+        .map(|(i, cell)| format!("{:<width$}", cell, width = widths[i]))
+        .collect();
+    println!("{}", line.join("  ").trim_end());
+}
@@ -0,0 +1,115 @@
+use crate::output_row::{print_table, round, OutputFormat, OutputRow};
+use crate::GroupStringStats;
+
+/// Renders a [`GroupStringStats`] map as a human-readable table, a
+/// delimiter-separated dump, or JSON/NDJSON, one entry per group, sorted by
+/// group key.
+pub struct OutputStringData {
+    headers: Vec<String>,
+    rows: Vec<OutputRow>,
+    json_rows: Vec<serde_json::Value>,
+    output_format: OutputFormat,
+}
+
+impl OutputStringData {
+    pub fn new(
+        group_string_stats: GroupStringStats,
+        input_delimiter: char,
+        output_format: OutputFormat,
+        decimals: usize,
+        top_k: Option<usize>,
+    ) -> Self {
+        let mut groups: Vec<_> = group_string_stats.into_iter().collect();
+        groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut headers = vec![
+            "group".to_string(),
+            "count".to_string(),
+            "null_count".to_string(),
+            "min_length".to_string(),
+            "max_length".to_string(),
+            "mean_length".to_string(),
+            "stddev_length".to_string(),
+            "cardinality".to_string(),
+        ];
+        if top_k.is_some() {
+            headers.push("top_k".to_string());
+        }
+
+        let mut rows = Vec::with_capacity(groups.len());
+        let mut json_rows = Vec::with_capacity(groups.len());
+        for (group, (value_stats, length_stats)) in groups {
+            let mut values = vec![
+                length_stats.count().to_string(),
+                value_stats.null_count().to_string(),
+                format!("{:.*}", decimals, length_stats.min()),
+                format!("{:.*}", decimals, length_stats.max()),
+                format!("{:.*}", decimals, length_stats.mean()),
+                format!("{:.*}", decimals, length_stats.stddev()),
+                value_stats.cardinality().to_string(),
+            ];
+
+            let mut json_row = serde_json::json!({
+                "group": group.clone(),
+                "count": length_stats.count(),
+                "null_count": value_stats.null_count(),
+                "min_length": round(length_stats.min(), decimals),
+                "max_length": round(length_stats.max(), decimals),
+                "mean_length": round(length_stats.mean(), decimals),
+                "stddev_length": round(length_stats.stddev(), decimals),
+                "cardinality": value_stats.cardinality(),
+            });
+
+            if let Some(k) = top_k {
+                let top_k_entries = value_stats.top_k(k).unwrap_or_default();
+                values.push(format_top_k(&top_k_entries));
+                json_row["top_k"] = top_k_entries
+                    .iter()
+                    .map(|(value, count, error)| {
+                        serde_json::json!({"value": value, "count": count, "max_error": error})
+                    })
+                    .collect();
+            }
+
+            rows.push(OutputRow::new(group, input_delimiter, values));
+            json_rows.push(json_row);
+        }
+
+        OutputStringData {
+            headers,
+            rows,
+            json_rows,
+            output_format,
+        }
+    }
+
+    pub fn print(&self) {
+        match self.output_format {
+            OutputFormat::Table => print_table(&self.headers, &self.rows),
+            OutputFormat::Delimited(delimiter) => {
+                println!("{}", self.headers.join(&delimiter.to_string()));
+                for row in &self.rows {
+                    println!("{}", row.to_delimited(delimiter));
+                }
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&self.json_rows).unwrap());
+            }
+            OutputFormat::Ndjson => {
+                for row in &self.json_rows {
+                    println!("{}", serde_json::to_string(row).unwrap());
+                }
+            }
+        }
+    }
+}
+
+/// Render top-k `(value, count, max_error)` entries as `value:count(+error)`,
+/// largest count first, separated by `;`.
+fn format_top_k(entries: &[(String, u64, u64)]) -> String {
+    entries
+        .iter()
+        .map(|(value, count, error)| format!("{value}:{count}(+{error})"))
+        .collect::<Vec<_>>()
+        .join(";")
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hasher;
+use twox_hash::XxHash64;
+
+/// Number of register-index bits (`b`) used by the HyperLogLog sketch.
+/// `m = 2^HLL_B` registers gives ~1-2% standard error at ~4KB per group.
+const HLL_B: u32 = 12;
+const HLL_M: usize = 1 << HLL_B;
+
+/// Fixed-memory approximate distinct-count sketch.
+///
+/// Each incoming value is hashed to a stable 64-bit digest; the top
+/// [`HLL_B`] bits select a register, and the register stores the longest
+/// run of leading zeros seen in the remaining bits (+1). Cardinality is
+/// estimated from the harmonic mean of `2^register` across all registers,
+/// per Flajolet et al., "HyperLogLog: the analysis of a near-optimal
+/// cardinality estimation algorithm" (2007).
+#[derive(Debug, Clone)]
+struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    fn new() -> Self {
+        HyperLogLog {
+            registers: vec![0; HLL_M],
+        }
+    }
+
+    fn add(&mut self, value: &str) {
+        let mut hasher = XxHash64::with_seed(0);
+        hasher.write(value.as_bytes());
+        let hash = hasher.finish();
+        let index = (hash >> (64 - HLL_B)) as usize;
+        let remaining = hash << HLL_B;
+        let rank = (if remaining == 0 { 64 } else { remaining.leading_zeros() } + 1) as u8;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Combine `other`'s registers into `self` by taking the register-wise
+    /// maximum rank, which is exact: it yields the sketch that would have
+    /// resulted from observing both streams together.
+    fn merge(&mut self, other: &HyperLogLog) {
+        for (a, b) in self.registers.iter_mut().zip(other.registers.iter()) {
+            if *b > *a {
+                *a = *b;
+            }
+        }
+    }
+
+    fn estimate(&self) -> f64 {
+        let m = HLL_M as f64;
+        let alpha_m = 0.7213 / (1.0 + 1.079 / m);
+        let sum_inv: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let raw_estimate = alpha_m * m * m / sum_inv;
+
+        if raw_estimate <= 2.5 * m {
+            let zero_registers = self.registers.iter().filter(|&&r| r == 0).count();
+            if zero_registers > 0 {
+                return m * (m / zero_registers as f64).ln();
+            }
+        } else if raw_estimate > (1u64 << 32) as f64 / 30.0 {
+            return -(2f64.powi(32)) * (1.0 - raw_estimate / 2f64.powi(32)).ln();
+        }
+        raw_estimate
+    }
+}
+
+/// How many more values [`SpaceSaving`] monitors than the `k` an output
+/// request asks for. Monitoring only `k` slots gives the worst possible
+/// error bound on the tail of the reported top-k, since every eviction
+/// competes directly with the values the caller cares about; a small
+/// multiple gives low-frequency noise somewhere else to evict instead.
+const SPACE_SAVING_CAPACITY_MULTIPLIER: usize = 4;
+
+/// Approximate heavy-hitter tracker using the Space-Saving algorithm.
+///
+/// Keeps at most `capacity` monitored values, each with an observed count
+/// and an over-estimate error bound. A value that is not monitored and the
+/// map is full evicts the monitored entry with the smallest count, then
+/// takes over its slot with `count = evicted_count + 1` and
+/// `error = evicted_count` so the true count is guaranteed to lie in
+/// `[count - error, count]`. See Metwally, Agrawal & El Abbadi,
+/// "Efficient Computation of Frequent and Top-k Elements in Data Streams" (2005).
+///
+/// A `capacity` of 0 is a legitimate degenerate case: no value is ever
+/// monitored and [`SpaceSaving::top_k`] reports nothing.
+#[derive(Debug, Clone)]
+struct SpaceSaving {
+    capacity: usize,
+    counts: HashMap<String, (u64, u64)>,
+}
+
+impl SpaceSaving {
+    fn new(capacity: usize) -> Self {
+        SpaceSaving {
+            capacity,
+            counts: HashMap::with_capacity(capacity),
+        }
+    }
+
+    fn add(&mut self, value: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(entry) = self.counts.get_mut(value) {
+            entry.0 += 1;
+            return;
+        }
+        if self.counts.len() < self.capacity {
+            self.counts.insert(value.to_string(), (1, 0));
+            return;
+        }
+        let evicted_key = self
+            .counts
+            .iter()
+            .min_by_key(|(_, &(count, _))| count)
+            .map(|(key, _)| key.clone())
+            .expect("capacity > 0 implies at least one monitored value");
+        let (evicted_count, _) = self.counts.remove(&evicted_key).unwrap();
+        self.counts.insert(value.to_string(), (evicted_count + 1, evicted_count));
+    }
+
+    /// Fold `other`'s monitored values into `self`. Values monitored by both
+    /// sides have their counts and errors summed; a value only `other`
+    /// monitors is inserted directly if there is room, otherwise it evicts
+    /// `self`'s smallest entry the same way [`SpaceSaving::add`] would,
+    /// carrying the evicted count into the new entry's error bound.
+    fn merge(&mut self, other: &SpaceSaving) {
+        if self.capacity == 0 {
+            return;
+        }
+        for (value, &(count, error)) in &other.counts {
+            if let Some(entry) = self.counts.get_mut(value) {
+                entry.0 += count;
+                entry.1 += error;
+                continue;
+            }
+            if self.counts.len() < self.capacity {
+                self.counts.insert(value.clone(), (count, error));
+                continue;
+            }
+            let evicted_key = self
+                .counts
+                .iter()
+                .min_by_key(|(_, &(evicted_count, _))| evicted_count)
+                .map(|(key, _)| key.clone())
+                .expect("capacity > 0 implies at least one monitored value");
+            let (evicted_count, evicted_error) = self.counts.remove(&evicted_key).unwrap();
+            self.counts
+                .insert(value.clone(), (count + evicted_count, error + evicted_count.max(evicted_error)));
+        }
+    }
+
+    /// The `k` monitored values with the largest counts, as `(value, count, max_error)`.
+    fn top_k(&self, k: usize) -> Vec<(String, u64, u64)> {
+        let mut entries: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(value, &(count, error))| (value.clone(), count, error))
+            .collect();
+        entries.sort_by_key(|&(_, count, _)| std::cmp::Reverse(count));
+        entries.truncate(k);
+        entries
+    }
+}
+
+/// How [`StringStats`] tracks distinct values for cardinality reporting.
+#[derive(Debug, Clone, Copy)]
+pub enum CardinalityMode {
+    /// Keep an exact set of distinct values, optionally capped at `cap`
+    /// entries to bound memory (`None` tracks without a bound).
+    Exact(Option<usize>),
+    /// Track an approximate count in fixed memory via HyperLogLog.
+    Hll,
+}
+
+#[derive(Debug, Clone)]
+enum Cardinality {
+    Exact { distinct: HashSet<String>, cap: Option<usize> },
+    Hll(HyperLogLog),
+}
+
+/// Online statistics for a single group of string values: a null count, a
+/// distinct-value cardinality tracked either exactly (optionally capped) or
+/// approximately via HyperLogLog, and optional approximate heavy hitters.
+#[derive(Debug, Clone)]
+pub struct StringStats {
+    null_count: usize,
+    cardinality: Cardinality,
+    top_k: Option<SpaceSaving>,
+}
+
+impl StringStats {
+    pub fn new(mode: CardinalityMode, top_k: Option<usize>) -> Self {
+        let cardinality = match mode {
+            CardinalityMode::Exact(cap) => Cardinality::Exact {
+                distinct: HashSet::new(),
+                cap,
+            },
+            CardinalityMode::Hll => Cardinality::Hll(HyperLogLog::new()),
+        };
+        StringStats {
+            null_count: 0,
+            cardinality,
+            top_k: top_k.map(|k| SpaceSaving::new(k * SPACE_SAVING_CAPACITY_MULTIPLIER)),
+        }
+    }
+
+    pub fn add(&mut self, value: String) {
+        if let Some(top_k) = &mut self.top_k {
+            top_k.add(&value);
+        }
+        match &mut self.cardinality {
+            Cardinality::Exact { distinct, cap } => {
+                if distinct.contains(&value) {
+                    return;
+                }
+                if let Some(cap) = cap {
+                    if distinct.len() >= *cap {
+                        return;
+                    }
+                }
+                distinct.insert(value);
+            }
+            Cardinality::Hll(hll) => hll.add(&value),
+        }
+    }
+
+    pub fn add_null(&mut self) {
+        self.null_count += 1;
+    }
+
+    /// Fold `other`'s observations into `self`: union the distinct sets (or
+    /// register-wise max the HyperLogLog sketches) and merge the top-k
+    /// heavy-hitter trackers. Assumes `other` was built with the same
+    /// [`CardinalityMode`] and `top_k` capacity as `self`.
+    pub fn merge(&mut self, other: &StringStats) {
+        self.null_count += other.null_count;
+        match (&mut self.cardinality, &other.cardinality) {
+            (Cardinality::Exact { distinct, cap }, Cardinality::Exact { distinct: other_distinct, .. }) => {
+                for value in other_distinct {
+                    if distinct.contains(value) {
+                        continue;
+                    }
+                    if let Some(cap) = cap {
+                        if distinct.len() >= *cap {
+                            continue;
+                        }
+                    }
+                    distinct.insert(value.clone());
+                }
+            }
+            (Cardinality::Hll(hll), Cardinality::Hll(other_hll)) => hll.merge(other_hll),
+            _ => {}
+        }
+        if let (Some(top_k), Some(other_top_k)) = (&mut self.top_k, &other.top_k) {
+            top_k.merge(other_top_k);
+        }
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.null_count
+    }
+
+    /// Distinct value count: exact when tracked with [`CardinalityMode::Exact`],
+    /// approximate when tracked with [`CardinalityMode::Hll`].
+    pub fn cardinality(&self) -> usize {
+        match &self.cardinality {
+            Cardinality::Exact { distinct, .. } => distinct.len(),
+            Cardinality::Hll(hll) => hll.estimate().round() as usize,
+        }
+    }
+
+    /// The `k` most frequent values seen, as `(value, count, max_error)`, if
+    /// a `top_k` capacity was passed to [`StringStats::new`].
+    pub fn top_k(&self, k: usize) -> Option<Vec<(String, u64, u64)>> {
+        self.top_k.as_ref().map(|top_k| top_k.top_k(k))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hll_estimate_is_within_a_few_percent_of_exact_cardinality() {
+        let mut hll = HyperLogLog::new();
+        let exact = 100_000;
+        for i in 0..exact {
+            hll.add(&format!("value-{i}"));
+        }
+        let estimate = hll.estimate();
+        let error = (estimate - exact as f64).abs() / exact as f64;
+        assert!(error < 0.03, "expected ~{exact}, got {estimate} ({error:.4} error)");
+    }
+
+    #[test]
+    fn hll_merge_matches_estimate_of_combined_stream() {
+        let mut a = HyperLogLog::new();
+        let mut b = HyperLogLog::new();
+        let mut combined = HyperLogLog::new();
+        for i in 0..20_000 {
+            a.add(&format!("value-{i}"));
+            combined.add(&format!("value-{i}"));
+        }
+        for i in 15_000..40_000 {
+            b.add(&format!("value-{i}"));
+            combined.add(&format!("value-{i}"));
+        }
+        a.merge(&b);
+        assert!((a.estimate() - combined.estimate()).abs() < 1.0);
+    }
+
+    #[test]
+    fn hll_repeated_values_do_not_inflate_estimate() {
+        let mut hll = HyperLogLog::new();
+        for _ in 0..10_000 {
+            hll.add("same-value");
+        }
+        assert!(hll.estimate() < 2.0);
+    }
+
+    #[test]
+    fn space_saving_zero_capacity_monitors_nothing_and_never_panics() {
+        let mut top_k = SpaceSaving::new(0);
+        top_k.add("a");
+        top_k.add("a");
+        top_k.add("b");
+        top_k.merge(&SpaceSaving::new(0));
+
+        assert_eq!(top_k.top_k(3), Vec::new());
+    }
+
+    #[test]
+    fn space_saving_exact_counts_under_capacity() {
+        let mut top_k = SpaceSaving::new(10);
+        for _ in 0..5 {
+            top_k.add("a");
+        }
+        for _ in 0..3 {
+            top_k.add("b");
+        }
+        top_k.add("c");
+
+        let entries = top_k.top_k(3);
+        assert_eq!(entries, vec![("a".to_string(), 5, 0), ("b".to_string(), 3, 0), ("c".to_string(), 1, 0)]);
+    }
+
+    #[test]
+    fn space_saving_evicts_with_error_bound_guaranteeing_true_count() {
+        let mut top_k = SpaceSaving::new(2);
+        top_k.add("a");
+        top_k.add("a");
+        top_k.add("a");
+        top_k.add("b");
+        // "c" evicts the smallest monitored entry ("b", count 1), so "c"
+        // starts at count 2 with error 1: true count of "c" is in [1, 2].
+        top_k.add("c");
+        top_k.add("c");
+
+        let entries = top_k.top_k(2);
+        let c_entry = entries.iter().find(|(value, ..)| value == "c").unwrap();
+        let (_, count, error) = c_entry;
+        assert!(count - error <= 2 && 2 <= *count, "true count 2 must lie in [{}, {}]", count - error, count);
+    }
+
+    #[test]
+    fn space_saving_merge_sums_counts_for_shared_values() {
+        let mut a = SpaceSaving::new(10);
+        a.add("x");
+        a.add("x");
+        let mut b = SpaceSaving::new(10);
+        b.add("x");
+        a.merge(&b);
+
+        let entries = a.top_k(1);
+        assert_eq!(entries, vec![("x".to_string(), 3, 0)]);
+    }
+}